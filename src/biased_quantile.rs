@@ -0,0 +1,192 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A bounded-error streaming quantile estimator (Cormode-Korn-Muthukrishnan-Srivastava).
+
+use std::f64;
+use std::fmt;
+
+/// A single sample tracked by `BiasedQuantile`: the observed value `v`, the
+/// difference in minimum rank `g` from the preceding sample, and the
+/// uncertainty `delta` in its maximum rank.
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    v: f64,
+    g: f64,
+    delta: f64,
+}
+
+/// Bounded-error streaming quantile estimator.
+///
+/// Unlike `Quantile`, which gives no guarantee on the error of its estimate,
+/// `BiasedQuantile` implements the CKMS algorithm: any queried quantile is
+/// within `epsilon` of its true rank, at the cost of retaining a bounded
+/// number of samples instead of a fixed five markers.
+#[derive(Clone)]
+pub struct BiasedQuantile {
+    epsilon: f64,
+    samples: Vec<Sample>,
+    n: u64,
+    since_compress: u64,
+}
+
+impl fmt::Debug for BiasedQuantile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} eps_quantile)", self.epsilon)
+    }
+}
+
+impl BiasedQuantile {
+    /// Constructor taking the allowed error bound epsilon (0 < epsilon < 1).
+    pub fn new(epsilon: f64) -> simple_error::SimpleResult<BiasedQuantile> {
+        if epsilon <= 0.0 || epsilon >= 1.0 {
+            return Err(simple_error::SimpleError::new(
+                "epsilon out of range 0 < epsilon < 1",
+            ));
+        }
+        Ok(BiasedQuantile {
+            epsilon: epsilon,
+            samples: Vec::new(),
+            n: 0,
+            since_compress: 0,
+        })
+    }
+
+    /// The error function `f(r, n)` bounding how many adjacent samples may be
+    /// merged together without violating the epsilon guarantee.
+    fn invariant(&self, n: f64) -> f64 {
+        2.0 * self.epsilon * n
+    }
+
+    /// Adds a value to the summary, NAN is ignored.
+    pub fn insert(&mut self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+
+        let pos = self
+            .samples
+            .iter()
+            .position(|s| s.v > v)
+            .unwrap_or(self.samples.len());
+
+        let delta = if pos == 0 || pos == self.samples.len() {
+            0.0
+        } else {
+            self.invariant(self.n as f64).floor()
+        };
+
+        self.samples.insert(
+            pos,
+            Sample {
+                v: v,
+                g: 1.0,
+                delta: delta,
+            },
+        );
+        self.n += 1;
+        self.since_compress += 1;
+
+        let compress_interval = (1.0 / (2.0 * self.epsilon)).max(1.0) as u64;
+        if self.since_compress >= compress_interval {
+            self.compress();
+            self.since_compress = 0;
+        }
+    }
+
+    /// Merges adjacent samples whose combined uncertainty still satisfies the
+    /// epsilon invariant, bounding the number of retained samples.
+    pub fn compress(&mut self) {
+        if self.samples.len() < 3 {
+            return;
+        }
+
+        let threshold = self.invariant(self.n as f64);
+        let mut i = self.samples.len() - 2;
+        loop {
+            let merged = self.samples[i].g + self.samples[i + 1].g + self.samples[i + 1].delta;
+            if merged <= threshold {
+                self.samples[i + 1].g += self.samples[i].g;
+                self.samples.remove(i);
+            }
+            if i == 0 {
+                break;
+            }
+            i -= 1;
+        }
+    }
+
+    /// Returns the estimated value at the phi-quantile (0.0..=1.0).
+    pub fn query(&self, phi: f64) -> f64 {
+        if self.samples.is_empty() {
+            return f64::NAN;
+        }
+
+        let n = self.n as f64;
+        let target = phi * n;
+        let f = self.invariant(n);
+
+        let mut r = 0.0;
+        for i in 0..self.samples.len() {
+            r += self.samples[i].g;
+            if r + self.samples[i].delta > target + f / 2.0 {
+                if i == 0 {
+                    return self.samples[0].v;
+                }
+                return self.samples[i - 1].v;
+            }
+        }
+        self.samples[self.samples.len() - 1].v
+    }
+
+    /// Returns the total number of values inserted into the summary.
+    pub fn count(&self) -> u64 {
+        self.n
+    }
+
+    /// Returns the number of samples currently retained by the summary.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if no values have been inserted yet.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BiasedQuantile;
+
+    #[test]
+    fn test_biased_quantile_range() {
+        let result = BiasedQuantile::new(0.0);
+        assert!(result.is_err());
+        let result = BiasedQuantile::new(1.0);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_biased_quantile() {
+        let mut q = BiasedQuantile::new(0.05).unwrap();
+        assert!(q.query(0.5).is_nan());
+
+        for i in 1..=100 {
+            q.insert(i as f64);
+        }
+
+        assert_eq!(q.count(), 100);
+        assert!(q.len() <= 100);
+
+        let median = q.query(0.5);
+        assert!((median - 50.0).abs() <= 0.05 * 100.0);
+
+        let min = q.query(0.0);
+        assert!((min - 1.0).abs() <= 0.05 * 100.0);
+
+        let max = q.query(1.0);
+        assert!((max - 100.0).abs() <= 0.05 * 100.0);
+    }
+}
@@ -0,0 +1,288 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A mergeable, fixed-size epsilon-approximate quantile summary (Zhang-Wang),
+//! for combining estimates computed independently across threads or hosts.
+
+use std::cmp::Ordering;
+use std::f64;
+use std::fmt;
+
+/// A value observed by a `FixedSizeEpsilonSummary` together with the bounds
+/// on its true rank within all the data absorbed so far.
+#[derive(Clone, Copy, Debug)]
+pub struct RankInfo {
+    /// The observed value.
+    pub val: f64,
+    /// Lower bound on the value's true rank.
+    pub rmin: u64,
+    /// Upper bound on the value's true rank.
+    pub rmax: u64,
+}
+
+/// Mergeable epsilon-approximate quantile summary.
+///
+/// Values are absorbed into a small buffer; once the buffer fills it is
+/// sorted and folded into a cascade of levels, each holding at most one
+/// block of `RankInfo`, following Munro-Paterson/Zhang-Wang: merging two
+/// blocks interleaves them and sums their rank bounds, and the merged block
+/// is pruned back down by keeping every other element. Because `merge`
+/// folds another summary's blocks through the same cascade, two summaries
+/// built independently (e.g. on separate threads) combine associatively.
+#[derive(Clone)]
+pub struct FixedSizeEpsilonSummary {
+    epsilon: f64,
+    block_size: usize,
+    buffer: Vec<RankInfo>,
+    levels: Vec<Option<Vec<RankInfo>>>,
+    n: u64,
+}
+
+impl fmt::Debug for FixedSizeEpsilonSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} eps, {} observations)", self.epsilon, self.n)
+    }
+}
+
+impl FixedSizeEpsilonSummary {
+    /// Constructor taking the allowed error bound epsilon (0 < epsilon < 1)
+    /// and the expected number of observations, used to size the internal
+    /// blocks.
+    pub fn new(epsilon: f64, expected_n: u64) -> simple_error::SimpleResult<FixedSizeEpsilonSummary> {
+        if epsilon <= 0.0 || epsilon >= 1.0 {
+            return Err(simple_error::SimpleError::new(
+                "epsilon out of range 0 < epsilon < 1",
+            ));
+        }
+        let n = (expected_n as f64).max(f64::consts::E);
+        let block_size = ((1.0 / epsilon) * n.log2()).ceil().max(2.0) as usize;
+
+        Ok(FixedSizeEpsilonSummary {
+            epsilon: epsilon,
+            block_size: block_size,
+            buffer: Vec::with_capacity(block_size),
+            levels: Vec::new(),
+            n: 0,
+        })
+    }
+
+    /// Adds a value to the summary.
+    pub fn update(&mut self, x: f64) {
+        self.buffer.push(RankInfo {
+            val: x,
+            rmin: 1,
+            rmax: 1,
+        });
+        self.n += 1;
+
+        if self.buffer.len() == self.block_size {
+            let block = Self::rank_sorted_block(self.buffer.split_off(0));
+            self.absorb_block(block, 0);
+        }
+    }
+
+    /// Folds `other`'s absorbed data into this summary. Two summaries built
+    /// independently can be combined with this, in either order.
+    pub fn merge(&mut self, other: &Self) {
+        self.n += other.n;
+
+        if !other.buffer.is_empty() {
+            let block = Self::rank_sorted_block(other.buffer.clone());
+            self.absorb_block(block, 0);
+        }
+
+        for (level, block) in other.levels.iter().enumerate() {
+            if let Some(b) = block {
+                self.absorb_block(b.clone(), level);
+            }
+        }
+    }
+
+    /// Returns the element whose rank bounds bracket `p * n`, the estimated
+    /// value at the p-quantile (0.0..=1.0).
+    pub fn query(&self, p: f64) -> f64 {
+        let combined = self.combine_all();
+        if combined.is_empty() {
+            return f64::NAN;
+        }
+
+        let target = p * self.n as f64;
+        if target < combined[0].rmin as f64 {
+            return combined[0].val;
+        }
+        for item in &combined {
+            if item.rmin as f64 <= target && target <= item.rmax as f64 {
+                return item.val;
+            }
+        }
+        combined[combined.len() - 1].val
+    }
+
+    /// Returns the total number of values absorbed into the summary.
+    pub fn count(&self) -> u64 {
+        self.n
+    }
+
+    fn rank_sorted_block(mut block: Vec<RankInfo>) -> Vec<RankInfo> {
+        block.sort_by(|a, b| a.val.partial_cmp(&b.val).unwrap_or(Ordering::Equal));
+        for (idx, item) in block.iter_mut().enumerate() {
+            item.rmin = idx as u64 + 1;
+            item.rmax = idx as u64 + 1;
+        }
+        block
+    }
+
+    fn absorb_block(&mut self, mut carry: Vec<RankInfo>, mut level: usize) {
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(None);
+            }
+            match self.levels[level].take() {
+                None => {
+                    self.levels[level] = Some(carry);
+                    break;
+                }
+                Some(existing) => {
+                    let merged = Self::merge_blocks(&existing, &carry);
+                    carry = Self::prune(merged);
+                    level += 1;
+                }
+            }
+        }
+    }
+
+    fn combine_all(&self) -> Vec<RankInfo> {
+        let mut acc = Vec::new();
+
+        if !self.buffer.is_empty() {
+            acc = Self::rank_sorted_block(self.buffer.clone());
+        }
+
+        for block in &self.levels {
+            if let Some(b) = block {
+                acc = if acc.is_empty() {
+                    b.clone()
+                } else {
+                    Self::merge_blocks(&acc, b)
+                };
+            }
+        }
+        acc
+    }
+
+    /// Interleaves two sorted blocks, recomputing each element's rank bounds
+    /// as the sum of the bounds it already carries and the bounds
+    /// contributed by the other block at that point.
+    fn merge_blocks(a: &[RankInfo], b: &[RankInfo]) -> Vec<RankInfo> {
+        let mut result = Vec::with_capacity(a.len() + b.len());
+        let mut i = 0;
+        let mut j = 0;
+
+        while i < a.len() || j < b.len() {
+            let take_a = j >= b.len() || (i < a.len() && a[i].val <= b[j].val);
+            if take_a {
+                let rmin_b = if j == 0 { 0 } else { b[j - 1].rmin };
+                let rmax_b = if j < b.len() { b[j].rmax } else { b[b.len() - 1].rmax };
+                result.push(RankInfo {
+                    val: a[i].val,
+                    rmin: a[i].rmin + rmin_b,
+                    rmax: a[i].rmax + rmax_b,
+                });
+                i += 1;
+            } else {
+                let rmin_a = if i == 0 { 0 } else { a[i - 1].rmin };
+                let rmax_a = if i < a.len() { a[i].rmax } else { a[a.len() - 1].rmax };
+                result.push(RankInfo {
+                    val: b[j].val,
+                    rmin: b[j].rmin + rmin_a,
+                    rmax: b[j].rmax + rmax_a,
+                });
+                j += 1;
+            }
+        }
+        result
+    }
+
+    /// Keeps every other element of a merged block, halving its size while
+    /// preserving the epsilon guarantee; the extremes are always retained.
+    fn prune(block: Vec<RankInfo>) -> Vec<RankInfo> {
+        if block.len() <= 2 {
+            return block;
+        }
+
+        let last = block.len() - 1;
+        let mut out = Vec::with_capacity(block.len() / 2 + 1);
+        let mut idx = 0;
+        while idx < block.len() {
+            out.push(block[idx]);
+            idx += 2;
+        }
+        if last % 2 != 0 {
+            out.push(block[last]);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedSizeEpsilonSummary;
+
+    #[test]
+    fn test_epsilon_summary_range() {
+        let result = FixedSizeEpsilonSummary::new(0.0, 100);
+        assert!(result.is_err());
+        let result = FixedSizeEpsilonSummary::new(1.0, 100);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_epsilon_summary_query() {
+        let mut s = FixedSizeEpsilonSummary::new(0.1, 100).unwrap();
+        assert!(s.query(0.5).is_nan());
+
+        for i in 1..=100 {
+            s.update(i as f64);
+        }
+
+        assert_eq!(s.count(), 100);
+        let median = s.query(0.5);
+        assert!((median - 50.0).abs() <= 0.1 * 100.0);
+
+        let min = s.query(0.0);
+        assert!((min - 1.0).abs() <= 0.1 * 100.0);
+    }
+
+    #[test]
+    fn test_epsilon_summary_query_low_tail() {
+        let mut s = FixedSizeEpsilonSummary::new(0.1, 5).unwrap();
+        for i in 1..=5 {
+            s.update(i as f64);
+        }
+
+        assert_eq!(s.query(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_epsilon_summary_merge() {
+        let mut a = FixedSizeEpsilonSummary::new(0.1, 100).unwrap();
+        for i in 1..=50 {
+            a.update(i as f64);
+        }
+
+        let mut b = FixedSizeEpsilonSummary::new(0.1, 100).unwrap();
+        for i in 51..=100 {
+            b.update(i as f64);
+        }
+
+        a.merge(&b);
+        assert_eq!(a.count(), 100);
+
+        let median = a.query(0.5);
+        assert!((median - 50.0).abs() <= 0.1 * 100.0);
+
+        let max = a.query(1.0);
+        assert!((max - 100.0).abs() <= 0.1 * 100.0);
+    }
+}
@@ -12,6 +12,8 @@ use std::vec::Vec;
 
 /// P2 Histogram Data Structure
 #[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde1", serde(try_from = "HistogramRaw"))]
 pub struct Histogram {
     q: Vec<f64>,
     n: Vec<f64>, // this is an integer but to avoid a lot of casting it is made a float
@@ -19,6 +21,43 @@ pub struct Histogram {
     cnt: u16,
 }
 
+/// Mirror of [`Histogram`]'s fields used to validate a deserialized value
+/// before trusting it, since `cnt` and the bucket vector lengths encode
+/// invariants that serde's derive alone cannot check.
+#[cfg(feature = "serde1")]
+#[derive(Deserialize)]
+struct HistogramRaw {
+    q: Vec<f64>,
+    n: Vec<f64>,
+    b: u16,
+    cnt: u16,
+}
+
+#[cfg(feature = "serde1")]
+impl std::convert::TryFrom<HistogramRaw> for Histogram {
+    type Error = simple_error::SimpleError;
+
+    fn try_from(raw: HistogramRaw) -> Result<Self, Self::Error> {
+        let expected = raw.b as usize + 1;
+        if raw.cnt as usize > expected {
+            return Err(simple_error::SimpleError::new(
+                "cnt out of range 0 <= cnt <= buckets + 1",
+            ));
+        }
+        if raw.q.len() != expected || raw.n.len() != expected {
+            return Err(simple_error::SimpleError::new(
+                "q/n length does not match buckets + 1",
+            ));
+        }
+        Ok(Histogram {
+            q: raw.q,
+            n: raw.n,
+            b: raw.b,
+            cnt: raw.cnt,
+        })
+    }
+}
+
 impl fmt::Debug for Histogram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({} buckets)", self.b)
@@ -137,6 +176,97 @@ impl Histogram {
         }
         return self.n[marker - 1] as usize;
     }
+
+    /// Returns the estimated value at the p-quantile (0.0..=1.0) by interpolating
+    /// between the two markers bracketing its rank, according to `interp`.
+    pub fn quantile(&self, p: f64, interp: Interpolation) -> f64 {
+        if self.cnt != 0 {
+            return f64::NAN;
+        }
+
+        let last = self.b as usize;
+        let h = p * (self.n[last] - 1.0) + 1.0;
+
+        let mut i = 0;
+        while i < last && self.n[i + 1] < h {
+            i += 1;
+        }
+        if i == last {
+            i -= 1;
+        }
+
+        let (lo, hi) = (self.n[i], self.n[i + 1]);
+        match interp {
+            Interpolation::Lower => self.q[i],
+            Interpolation::Higher => self.q[i + 1],
+            Interpolation::Nearest => {
+                if h - lo <= hi - h {
+                    self.q[i]
+                } else {
+                    self.q[i + 1]
+                }
+            }
+            Interpolation::Midpoint => (self.q[i] + self.q[i + 1]) / 2.0,
+            Interpolation::Linear => {
+                if hi == lo {
+                    self.q[i]
+                } else {
+                    self.q[i] + (h - lo) / (hi - lo) * (self.q[i + 1] - self.q[i])
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl Histogram {
+    /// Returns the number of buckets this histogram was constructed with.
+    pub(crate) fn buckets(&self) -> u16 {
+        self.b
+    }
+
+    /// Renders this histogram into the Prometheus/OpenMetrics text exposition
+    /// format: one `name_bucket{le="..."}` line per marker, using the
+    /// marker's value as the bucket's cumulative upper bound, then a
+    /// `name_bucket{le="+Inf"}` line with the total count, followed by
+    /// `name_count` and, if `sum` is supplied, `name_sum`.
+    pub fn to_prometheus(&self, name: &str, help: &str, sum: Option<f64>) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("# HELP {} {}\n", name, help));
+        out.push_str(&format!("# TYPE {} histogram\n", name));
+
+        let buckets = self.b as usize + 1;
+        for marker in 1..=buckets {
+            out.push_str(&format!(
+                "{}_bucket{{le=\"{}\"}} {}\n",
+                name,
+                self.estimate(marker),
+                self.count(marker)
+            ));
+        }
+        out.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, self.count(buckets)));
+        out.push_str(&format!("{}_count {}\n", name, self.count(buckets)));
+        if let Some(sum) = sum {
+            out.push_str(&format!("{}_sum {}\n", name, sum));
+        }
+        out
+    }
+}
+
+/// Interpolation method used by [`Histogram::quantile`] when the requested
+/// rank falls between two markers.
+#[derive(Clone, Copy, Debug)]
+pub enum Interpolation {
+    /// Take the value at the lower marker.
+    Lower,
+    /// Take the value at the higher marker.
+    Higher,
+    /// Round to whichever marker's rank is closer.
+    Nearest,
+    /// Average the two bracketing marker values.
+    Midpoint,
+    /// Linearly interpolate between the two bracketing marker values.
+    Linear,
 }
 
 #[cfg(test)]
@@ -198,4 +328,87 @@ mod tests {
             i += 1;
         }
     }
+
+    #[test]
+    fn test_histogram_quantile() {
+        use super::Interpolation;
+
+        let mut q = Histogram::new(4).unwrap();
+        assert!(q.quantile(0.5, Interpolation::Linear).is_nan());
+        for x in &td::OBS {
+            q.add(*x);
+        }
+
+        let last = *td::COUNT_RESULTS.last().unwrap() as f64;
+        let mut i = 0;
+        for x in &td::FULL_RESULTS {
+            let p = (td::COUNT_RESULTS[i] as f64 - 1.0) / (last - 1.0);
+            let rpq = q.quantile(p, Interpolation::Linear);
+            assert!(
+                (rpq - x).abs() < 0.00001,
+                format!("p: {} received:{} expected:{}", p, rpq, x)
+            );
+            i += 1;
+        }
+
+        let lower = q.quantile(0.6, Interpolation::Lower);
+        let higher = q.quantile(0.6, Interpolation::Higher);
+        let midpoint = q.quantile(0.6, Interpolation::Midpoint);
+        let nearest = q.quantile(0.6, Interpolation::Nearest);
+        assert!(lower <= higher);
+        assert_eq!(midpoint, (lower + higher) / 2.0);
+        assert!(nearest == lower || nearest == higher);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_histogram_to_prometheus() {
+        let mut q = Histogram::new(4).unwrap();
+        for x in &td::OBS {
+            q.add(*x);
+        }
+
+        let text = q.to_prometheus("latency_seconds", "Request latency", Some(123.4));
+        assert!(text.contains("# HELP latency_seconds Request latency\n"));
+        assert!(text.contains("# TYPE latency_seconds histogram\n"));
+        assert!(text.contains("latency_seconds_bucket{le=\"38.62\"} 20\n"));
+        assert!(text.contains("latency_seconds_bucket{le=\"+Inf\"} 20\n"));
+        assert!(text.contains("latency_seconds_count 20\n"));
+        assert!(text.contains("latency_seconds_sum 123.4\n"));
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn test_histogram_serde_roundtrip() {
+        let mut q = Histogram::new(4).unwrap();
+        for x in &td::OBS {
+            q.add(*x);
+        }
+
+        let json = ::serde_json::to_string(&q).unwrap();
+        let round: Histogram = ::serde_json::from_str(&json).unwrap();
+
+        let mut i = 1;
+        while i <= 5 {
+            assert_eq!(q.estimate(i), round.estimate(i));
+            assert_eq!(q.count(i), round.count(i));
+            i += 1;
+        }
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn test_histogram_serde_rejects_bad_cnt() {
+        let json = r#"{"q":[0.0,0.0,0.0,0.0,0.0],"n":[1.0,2.0,3.0,4.0,5.0],"b":4,"cnt":6}"#;
+        let result: Result<Histogram, _> = ::serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn test_histogram_serde_rejects_wrong_length() {
+        let json = r#"{"q":[0.0,0.0,0.0,0.0],"n":[1.0,2.0,3.0,4.0],"b":4,"cnt":0}"#;
+        let result: Result<Histogram, _> = ::serde_json::from_str(json);
+        assert!(result.is_err());
+    }
 }
@@ -17,12 +17,29 @@
 )]
 
 extern crate simple_error;
+#[cfg(feature = "serde1")]
+extern crate serde;
+#[cfg(feature = "serde1")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "prometheus")]
+extern crate prometheus;
+#[cfg(all(test, feature = "serde1"))]
+extern crate serde_json;
 
-pub use self::histogram::Histogram;
+pub use self::biased_quantile::BiasedQuantile;
+pub use self::epsilon_summary::{FixedSizeEpsilonSummary, RankInfo};
+pub use self::histogram::{Histogram, Interpolation};
 pub use self::quantile::Quantile;
+#[cfg(feature = "prometheus")]
+pub use self::prometheus_export::register_histogram;
 
+mod biased_quantile;
+mod epsilon_summary;
 mod histogram;
 mod quantile;
+#[cfg(feature = "prometheus")]
+mod prometheus_export;
 
 fn parabolic(i: usize, d: f64, q: &[f64], n: &[f64]) -> f64 {
     return q[i]
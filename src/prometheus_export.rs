@@ -0,0 +1,31 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Registers a [`Histogram`]'s marker counts into a `prometheus::Registry`,
+//! for services that already scrape Prometheus and want P2-estimated
+//! percentiles without standing up a separate reservoir.
+
+use histogram::Histogram;
+
+/// Registers each marker's cumulative observation count as a gauge labelled
+/// by its `le` bucket boundary, under `{prefix}_bucket`.
+pub fn register_histogram(
+    histogram: &Histogram,
+    registry: &prometheus::Registry,
+    prefix: &str,
+) -> prometheus::Result<()> {
+    let opts = prometheus::Opts::new(
+        format!("{}_bucket", prefix),
+        "P2-estimated histogram bucket counts",
+    );
+    let gauge_vec = prometheus::GaugeVec::new(opts, &["le"])?;
+
+    for marker in 1..=(histogram.buckets() as usize + 1) {
+        gauge_vec
+            .with_label_values(&[&histogram.estimate(marker).to_string()])
+            .set(histogram.count(marker) as f64);
+    }
+
+    registry.register(Box::new(gauge_vec))
+}
@@ -12,6 +12,8 @@ const QUANTILE_MARKERS: usize = 5;
 
 /// P2 Quantile Data Structure
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde1", serde(try_from = "QuantileRaw"))]
 pub struct Quantile {
     q: [f64; QUANTILE_MARKERS],
     n: [f64; QUANTILE_MARKERS], // this is an integer but to avoid a lot of casting it is made a float
@@ -20,6 +22,40 @@ pub struct Quantile {
     cnt: u8,
 }
 
+/// Mirror of [`Quantile`]'s fields used to validate a deserialized value
+/// before trusting it: `cnt` must not exceed the number of markers, which
+/// serde's derive alone cannot check. The marker arrays' lengths are fixed
+/// by their type; their contents are not checked.
+#[cfg(feature = "serde1")]
+#[derive(Deserialize)]
+struct QuantileRaw {
+    q: [f64; QUANTILE_MARKERS],
+    n: [f64; QUANTILE_MARKERS],
+    n1: [f64; QUANTILE_MARKERS],
+    p: f32,
+    cnt: u8,
+}
+
+#[cfg(feature = "serde1")]
+impl std::convert::TryFrom<QuantileRaw> for Quantile {
+    type Error = simple_error::SimpleError;
+
+    fn try_from(raw: QuantileRaw) -> Result<Self, Self::Error> {
+        if raw.cnt as usize > QUANTILE_MARKERS {
+            return Err(simple_error::SimpleError::new(
+                "cnt out of range 0 <= cnt <= markers",
+            ));
+        }
+        Ok(Quantile {
+            q: raw.q,
+            n: raw.n,
+            n1: raw.n1,
+            p: raw.p,
+            cnt: raw.cnt,
+        })
+    }
+}
+
 impl fmt::Debug for Quantile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({} p_quantile)", self.p)
@@ -229,4 +265,31 @@ mod tests {
             i += 1;
         }
     }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn test_quantile_serde_roundtrip() {
+        let mut q = Quantile::new(0.5).unwrap();
+        for x in &td::OBS {
+            q.add(*x);
+        }
+
+        let json = ::serde_json::to_string(&q).unwrap();
+        let round: Quantile = ::serde_json::from_str(&json).unwrap();
+
+        let mut i = 1;
+        while i <= super::QUANTILE_MARKERS {
+            assert_eq!(q.estimate(i), round.estimate(i));
+            assert_eq!(q.count(i), round.count(i));
+            i += 1;
+        }
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn test_quantile_serde_rejects_bad_cnt() {
+        let json = r#"{"q":[0.0,0.0,0.0,0.0,0.0],"n":[1.0,2.0,3.0,4.0,5.0],"n1":[1.0,1.0,1.0,1.0,5.0],"p":0.5,"cnt":255}"#;
+        let result: Result<Quantile, _> = ::serde_json::from_str(json);
+        assert!(result.is_err());
+    }
 }